@@ -4,4 +4,40 @@ fn main() {
         .compile("egg-netlist-synthesizer");
 
     println!("cargo:rerun-if-changed=src/lib.rs");
+
+    build_tree_sitter_grammar();
+}
+
+// `tree-sitter generate` turns tree-sitter-egg-netlist/grammar.js into a C
+// parser. Nothing in this tree commits that generated output, and shelling
+// out to the CLI unconditionally made `cargo build` hard-panic for anyone
+// without it installed. Instead, compile `src/parser.c` in only when it's
+// already present on disk, and gate `cst`/the `check` subcommand behind the
+// `tree_sitter_grammar` cfg this emits: a maintainer who regenerates and
+// commits `src/parser.c` (after editing `grammar.js`) gets a working `check`
+// subcommand, and everyone else still gets a reproducible build.
+fn build_tree_sitter_grammar() {
+    println!("cargo::rustc-check-cfg=cfg(tree_sitter_grammar)");
+
+    let grammar_dir = "tree-sitter-egg-netlist";
+    let parser_c = format!("{}/src/parser.c", grammar_dir);
+    println!("cargo:rerun-if-changed={}/grammar.js", grammar_dir);
+    println!("cargo:rerun-if-changed={}", parser_c);
+
+    if !std::path::Path::new(&parser_c).exists() {
+        println!(
+            "cargo:warning=tree-sitter-egg-netlist/src/parser.c not found; \
+             run `tree-sitter generate --no-bindings` in {} and commit the \
+             result to enable `cst` and the `check` subcommand",
+            grammar_dir
+        );
+        return;
+    }
+
+    cc::Build::new()
+        .include(format!("{}/src", grammar_dir))
+        .file(&parser_c)
+        .compile("tree-sitter-egg-netlist");
+
+    println!("cargo::rustc-cfg=tree_sitter_grammar");
 }
@@ -0,0 +1,243 @@
+// Walks the tree-sitter CST for the surface syntax (grammar in
+// `tree-sitter-egg-netlist/grammar.js`) into one `RecExpr<BooleanLanguage>`
+// per top-level `module`, checking along the way that every identifier an
+// `assign` references is either a declared port or the name of an earlier
+// `assign` in the same module.
+
+use crate::diagnostics::Diagnostic;
+use crate::BooleanLanguage;
+use egg::{Id, RecExpr};
+use std::collections::HashSet;
+use tree_sitter::{Language, Node, Tree};
+
+extern "C" {
+    fn tree_sitter_egg_netlist() -> Language;
+}
+
+// The tree-sitter `Language` for the surface syntax grammar.
+pub fn language() -> Language {
+    unsafe { tree_sitter_egg_netlist() }
+}
+
+// Lower every module in a parsed `Tree` into a `RecExpr<BooleanLanguage>`,
+// or collect every syntax/name-resolution problem found along the way.
+// Any named child of the root that isn't a `module` (including a second
+// `source_file` worth of trailing content after the grammar's error
+// recovery) is reported rather than silently dropped.
+pub fn cst_to_recexpr(tree: &Tree, source: &str) -> Result<Vec<RecExpr<BooleanLanguage>>, Vec<Diagnostic>> {
+    let root = tree.root_node();
+
+    let mut errors = vec![];
+    collect_syntax_errors(root, &mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut modules = vec![];
+    let mut cursor = root.walk();
+    for child in root.named_children(&mut cursor) {
+        if child.kind() != "module" {
+            errors.push(Diagnostic::new(
+                format!("unexpected top-level `{}`, expected a module", child.kind()),
+                child.byte_range(),
+            ));
+            continue;
+        }
+        match lower_module(child, source) {
+            Ok(expr) => modules.push(expr),
+            Err(mut module_errors) => errors.append(&mut module_errors),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    if modules.is_empty() {
+        return Err(vec![Diagnostic::new("expected at least one module", 0..source.len())]);
+    }
+
+    Ok(modules)
+}
+
+// Recursively collect any ERROR/MISSING nodes the grammar left behind, so
+// `check` can report them with precise node ranges.
+fn collect_syntax_errors(node: Node, errors: &mut Vec<Diagnostic>) {
+    if node.is_error() || node.is_missing() {
+        errors.push(Diagnostic::new(
+            format!("unexpected syntax near `{}`", node.kind()),
+            node.byte_range(),
+        ));
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_syntax_errors(child, errors);
+    }
+}
+
+fn lower_module(node: Node, source: &str) -> Result<RecExpr<BooleanLanguage>, Vec<Diagnostic>> {
+    // Every named `identifier` child except the module's own (fielded)
+    // name is a declared port.
+    let module_name = node.child_by_field_name("name").map(|n| n.id());
+    let mut declared: HashSet<String> = HashSet::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == "identifier" && Some(child.id()) != module_name {
+            declared.insert(text(child, source).to_string());
+        }
+    }
+
+    let mut expr = RecExpr::default();
+    let mut errors = vec![];
+    let mut stmt_ids = vec![];
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == "assign" {
+            match lower_assign(child, source, &mut expr, &mut declared) {
+                Ok(id) => stmt_ids.push(id),
+                Err(mut assign_errors) => errors.append(&mut assign_errors),
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    expr.add(BooleanLanguage::Module(stmt_ids));
+    Ok(expr)
+}
+
+fn lower_assign(
+    node: Node,
+    source: &str,
+    expr: &mut RecExpr<BooleanLanguage>,
+    declared: &mut HashSet<String>,
+) -> Result<Id, Vec<Diagnostic>> {
+    let name_node = node
+        .child_by_field_name("name")
+        .ok_or_else(|| vec![Diagnostic::new("assign is missing a name", node.byte_range())])?;
+    let value_node = node
+        .child_by_field_name("value")
+        .ok_or_else(|| vec![Diagnostic::new("assign is missing a value", node.byte_range())])?;
+
+    let mut errors = vec![];
+    check_identifiers_declared(value_node, source, declared, &mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let name = text(name_node, source).to_string();
+    let name_id = expr.add(BooleanLanguage::Symbol(name.clone().into()));
+    let value_id = lower_expr(value_node, source, expr)?;
+    declared.insert(name);
+
+    Ok(expr.add(BooleanLanguage::Let([name_id, value_id])))
+}
+
+// Report every identifier under `node` that isn't a declared port or the
+// name of an earlier `assign`, e.g. a typo'd port name.
+fn check_identifiers_declared(
+    node: Node,
+    source: &str,
+    declared: &HashSet<String>,
+    errors: &mut Vec<Diagnostic>,
+) {
+    if node.kind() == "identifier" {
+        let name = text(node, source);
+        if !declared.contains(name) {
+            errors.push(Diagnostic::new(
+                format!("reference to undeclared identifier `{}`", name),
+                node.byte_range(),
+            ));
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        check_identifiers_declared(child, source, declared, errors);
+    }
+}
+
+fn lower_expr(node: Node, source: &str, expr: &mut RecExpr<BooleanLanguage>) -> Result<Id, Vec<Diagnostic>> {
+    match node.kind() {
+        "identifier" => Ok(expr.add(BooleanLanguage::Symbol(text(node, source).into()))),
+        "paren_expr" => {
+            let inner = node
+                .named_child(0)
+                .ok_or_else(|| vec![Diagnostic::new("empty parentheses", node.byte_range())])?;
+            lower_expr(inner, source, expr)
+        }
+        "unary_expr" => {
+            let operand = node
+                .named_child(0)
+                .ok_or_else(|| vec![Diagnostic::new("`!` is missing an operand", node.byte_range())])?;
+            let operand_id = lower_expr(operand, source, expr)?;
+            Ok(expr.add(BooleanLanguage::Not([operand_id])))
+        }
+        "binary_expr" => {
+            let lhs = node
+                .child(0)
+                .ok_or_else(|| vec![Diagnostic::new("missing left operand", node.byte_range())])?;
+            let op = node
+                .child(1)
+                .ok_or_else(|| vec![Diagnostic::new("missing operator", node.byte_range())])?;
+            let rhs = node
+                .child(2)
+                .ok_or_else(|| vec![Diagnostic::new("missing right operand", node.byte_range())])?;
+
+            let lhs_id = lower_expr(lhs, source, expr)?;
+            let rhs_id = lower_expr(rhs, source, expr)?;
+            match text(op, source) {
+                "&" => Ok(expr.add(BooleanLanguage::And([lhs_id, rhs_id]))),
+                "|" => Ok(expr.add(BooleanLanguage::Or([lhs_id, rhs_id]))),
+                other => Err(vec![Diagnostic::new(
+                    format!("unknown operator `{}`", other),
+                    op.byte_range(),
+                )]),
+            }
+        }
+        other => Err(vec![Diagnostic::new(
+            format!("unexpected node kind `{}`", other),
+            node.byte_range(),
+        )]),
+    }
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    &source[node.byte_range()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_source(source: &str) -> Tree {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(language()).expect("failed to load the egg-netlist grammar");
+        parser.parse(source, None).expect("tree-sitter failed to produce a tree")
+    }
+
+    #[test]
+    fn resolves_ports_and_earlier_assigns() {
+        let source = "module t(a, b, y);\ny = a & b;\nendmodule\n";
+        let tree = parse_source(source);
+        let modules = cst_to_recexpr(&tree, source).expect("should lower cleanly");
+        assert_eq!(modules.len(), 1);
+    }
+
+    #[test]
+    fn rejects_undeclared_identifier() {
+        let source = "module t(a, b, y);\ny = a & c;\nendmodule\n";
+        let tree = parse_source(source);
+        let diagnostics = cst_to_recexpr(&tree, source).expect_err("`c` is not declared");
+        assert!(diagnostics.iter().any(|d| d.message.contains("`c`")));
+    }
+
+    #[test]
+    fn walks_every_top_level_module() {
+        let source = "module a(x, y);\ny = x;\nendmodule\nmodule b(x, y);\ny = !x;\nendmodule\n";
+        let tree = parse_source(source);
+        let modules = cst_to_recexpr(&tree, source).expect("should lower cleanly");
+        assert_eq!(modules.len(), 2);
+    }
+}
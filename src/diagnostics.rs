@@ -0,0 +1,117 @@
+// A `Diagnostic` pairs a message with the byte span it refers to, and knows
+// how to render itself against the source text that span was taken from.
+
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Range<usize>) -> Diagnostic {
+        Diagnostic {
+            message: message.into(),
+            span,
+        }
+    }
+
+    // Render this diagnostic against `source`: the offending line, followed
+    // by a caret underline of the span.
+    pub fn render(&self, source: &str) -> String {
+        let (line_no, col, line) = locate(source, self.span.start);
+        let underline_len = self.span.end.saturating_sub(self.span.start).max(1);
+        format!(
+            "error: {}\n  --> line {}:{}\n  | {}\n  | {}{}",
+            self.message,
+            line_no,
+            col,
+            line,
+            " ".repeat(col.saturating_sub(1)),
+            "^".repeat(underline_len)
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// Locate the 1-indexed line number, 1-indexed column, and full line text
+// containing byte offset `offset` in `source`.
+fn locate(source: &str, offset: usize) -> (usize, usize, &str) {
+    let mut line_no = 1;
+    let mut line_start = 0;
+    for (i, ch) in source.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if ch == '\n' {
+            line_no += 1;
+            line_start = i + 1;
+        }
+    }
+    let line_end = source[line_start..]
+        .find('\n')
+        .map(|i| line_start + i)
+        .unwrap_or_else(|| source.len());
+    (line_no, offset - line_start + 1, &source[line_start..line_end])
+}
+
+// Render a batch of diagnostics against `source`, one after another.
+pub fn render_all(diagnostics: &[Diagnostic], source: &str) -> String {
+    diagnostics
+        .iter()
+        .map(|d| d.render(source))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_points_at_the_offending_line_and_column() {
+        let source = "line one\nline two\nline three\n";
+        let span_start = source.find("two").unwrap();
+        let diagnostic = Diagnostic::new("bad thing", span_start..span_start + 3);
+
+        let rendered = diagnostic.render(source);
+
+        assert!(rendered.contains("error: bad thing"));
+        assert!(rendered.contains("line 2:6"));
+        assert!(rendered.contains("| line two"));
+        assert!(rendered.contains("|      ^^^"));
+    }
+
+    #[test]
+    fn render_on_first_line_has_no_leading_newline_scan() {
+        let diagnostic = Diagnostic::new("oops", 0..1);
+        let rendered = diagnostic.render("x = 1;");
+        assert!(rendered.contains("line 1:1"));
+        assert!(rendered.contains("^"));
+    }
+
+    #[test]
+    fn render_underlines_at_least_one_column_for_an_empty_span() {
+        let diagnostic = Diagnostic::new("empty span", 3..3);
+        let rendered = diagnostic.render("abcdef");
+        assert!(rendered.ends_with('^'));
+        assert_eq!(rendered.matches('^').count(), 1);
+    }
+
+    #[test]
+    fn render_all_joins_diagnostics_with_a_blank_line() {
+        let source = "a\nb\n";
+        let diagnostics = vec![Diagnostic::new("first", 0..1), Diagnostic::new("second", 2..3)];
+        let rendered = render_all(&diagnostics, source);
+        assert!(rendered.contains("error: first"));
+        assert!(rendered.contains("error: second"));
+        assert_eq!(rendered.matches("\n\n").count(), 1);
+    }
+}
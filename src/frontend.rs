@@ -0,0 +1,329 @@
+// A small frontend for a structural netlist surface syntax, e.g.:
+//
+//   module top(a, b, c, y);
+//   assign y = (a & b) | !c;
+//   endmodule
+//
+// Lowers directly to a `RecExpr<BooleanLanguage>`, bypassing the need for
+// callers to hand-assemble an s-expression.
+
+use crate::BooleanLanguage;
+use egg::{Id, RecExpr};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Equals,
+    Semi,
+    Comma,
+    Module,
+    Assign,
+    Endmodule,
+}
+
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = vec![];
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '&' => {
+                tokens.push(Token::And);
+                i += 1;
+            }
+            '|' => {
+                tokens.push(Token::Or);
+                i += 1;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '=' => {
+                tokens.push(Token::Equals);
+                i += 1;
+            }
+            ';' => {
+                tokens.push(Token::Semi);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let ident: String = chars[start..i].iter().collect();
+                tokens.push(match ident.as_str() {
+                    "module" => Token::Module,
+                    "assign" => Token::Assign,
+                    "endmodule" => Token::Endmodule,
+                    _ => Token::Ident(ident),
+                });
+            }
+            _ => return Err(ParseError(format!("unexpected character `{}`", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    expr: RecExpr<BooleanLanguage>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token]) -> Parser<'a> {
+        Parser {
+            tokens,
+            pos: 0,
+            expr: RecExpr::default(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), ParseError> {
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(ParseError(format!(
+                "expected {:?}, found {:?}",
+                expected, token
+            ))),
+            None => Err(ParseError(format!(
+                "expected {:?}, found end of input",
+                expected
+            ))),
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, ParseError> {
+        match self.advance() {
+            Some(Token::Ident(name)) => Ok(name.clone()),
+            Some(token) => Err(ParseError(format!("expected identifier, found {:?}", token))),
+            None => Err(ParseError("expected identifier, found end of input".into())),
+        }
+    }
+
+    // module := "module" ident "(" ident ("," ident)* ")" ";" stmt* "endmodule"
+    //
+    // Builds the module into its own, freshly-reset `RecExpr` and returns it,
+    // so that a source file containing several modules lowers to one
+    // self-contained tree per module rather than one tree referencing nodes
+    // left over from a previous module.
+    fn parse_module(&mut self) -> Result<RecExpr<BooleanLanguage>, ParseError> {
+        self.expr = RecExpr::default();
+
+        self.expect(&Token::Module)?;
+        self.expect_ident()?; // module name, not represented in the AST
+        self.expect(&Token::LParen)?;
+        self.expect_ident()?;
+        while self.peek() == Some(&Token::Comma) {
+            self.advance();
+            self.expect_ident()?;
+        }
+        self.expect(&Token::RParen)?;
+        self.expect(&Token::Semi)?;
+
+        let mut stmt_ids = vec![];
+        while self.peek() == Some(&Token::Assign) {
+            stmt_ids.push(self.parse_assign()?);
+        }
+        self.expect(&Token::Endmodule)?;
+
+        self.expr.add(BooleanLanguage::Module(stmt_ids));
+        Ok(std::mem::take(&mut self.expr))
+    }
+
+    // stmt := "assign" ident "=" expr ";"
+    fn parse_assign(&mut self) -> Result<Id, ParseError> {
+        self.expect(&Token::Assign)?;
+        let name = self.expect_ident()?;
+        self.expect(&Token::Equals)?;
+        let expr_id = self.parse_or()?;
+        self.expect(&Token::Semi)?;
+
+        let name_id = self.expr.add(BooleanLanguage::Symbol(name.into()));
+        Ok(self.expr.add(BooleanLanguage::Let([name_id, expr_id])))
+    }
+
+    // or := and ("|" and)*
+    fn parse_or(&mut self) -> Result<Id, ParseError> {
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.advance();
+            let rhs = self.parse_and()?;
+            lhs = self.expr.add(BooleanLanguage::Or([lhs, rhs]));
+        }
+        Ok(lhs)
+    }
+
+    // and := unary ("&" unary)*
+    fn parse_and(&mut self) -> Result<Id, ParseError> {
+        let mut lhs = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.advance();
+            let rhs = self.parse_unary()?;
+            lhs = self.expr.add(BooleanLanguage::And([lhs, rhs]));
+        }
+        Ok(lhs)
+    }
+
+    // unary := "!" unary | primary
+    fn parse_unary(&mut self) -> Result<Id, ParseError> {
+        if self.peek() == Some(&Token::Not) {
+            self.advance();
+            let id = self.parse_unary()?;
+            return Ok(self.expr.add(BooleanLanguage::Not([id])));
+        }
+        self.parse_primary()
+    }
+
+    // primary := ident | "(" or ")"
+    fn parse_primary(&mut self) -> Result<Id, ParseError> {
+        match self.advance() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                Ok(self.expr.add(BooleanLanguage::Symbol(name.into())))
+            }
+            Some(Token::LParen) => {
+                let id = self.parse_or()?;
+                self.expect(&Token::RParen)?;
+                Ok(id)
+            }
+            Some(token) => Err(ParseError(format!(
+                "expected identifier or `(`, found {:?}",
+                token
+            ))),
+            None => Err(ParseError("expected identifier or `(`, found end of input".into())),
+        }
+    }
+}
+
+// Parse every module in a structural netlist source string into a
+// `RecExpr<BooleanLanguage>` each. Errors if any input remains once modules
+// stop matching, so trailing garbage after the last `endmodule` is rejected
+// rather than silently ignored.
+pub fn parse(source: &str) -> Result<Vec<RecExpr<BooleanLanguage>>, ParseError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser::new(&tokens);
+
+    let mut modules = vec![];
+    while parser.peek().is_some() {
+        modules.push(parser.parse_module()?);
+    }
+
+    if modules.is_empty() {
+        return Err(ParseError("expected at least one module".into()));
+    }
+
+    Ok(modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let source = "module t(a,b,c,y); assign y = a & b | !c; endmodule";
+        let modules = parse(source).unwrap();
+        assert_eq!(modules.len(), 1);
+        let expr = &modules[0];
+
+        let stmt_ids = match expr.as_ref().last().unwrap() {
+            BooleanLanguage::Module(ids) => ids,
+            other => panic!("expected Module, found {:?}", other),
+        };
+        assert_eq!(stmt_ids.len(), 1);
+
+        let value_id = match &expr[stmt_ids[0]] {
+            BooleanLanguage::Let([_, value]) => *value,
+            other => panic!("expected Let, found {:?}", other),
+        };
+
+        // `a & b | !c` should parse as `(a & b) | (!c)`: `&` binds tighter
+        // than `|`, and unary `!` binds tighter than both.
+        match &expr[value_id] {
+            BooleanLanguage::Or([lhs, rhs]) => {
+                assert!(matches!(expr[*lhs], BooleanLanguage::And(_)));
+                assert!(matches!(expr[*rhs], BooleanLanguage::Not(_)));
+            }
+            other => panic!("expected top-level Or, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parens_override_precedence() {
+        let source = "module t(a,b,c,y); assign y = a & (b | c); endmodule";
+        let expr = &parse(source).unwrap()[0];
+
+        let stmt_ids = match expr.as_ref().last().unwrap() {
+            BooleanLanguage::Module(ids) => ids,
+            other => panic!("expected Module, found {:?}", other),
+        };
+        let value_id = match &expr[stmt_ids[0]] {
+            BooleanLanguage::Let([_, value]) => *value,
+            other => panic!("expected Let, found {:?}", other),
+        };
+
+        match &expr[value_id] {
+            BooleanLanguage::And([_, rhs]) => {
+                assert!(matches!(expr[*rhs], BooleanLanguage::Or(_)));
+            }
+            other => panic!("expected top-level And, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        let source = "module t(a); assign a = a; endmodule garbage";
+        assert!(parse(source).is_err());
+    }
+
+    #[test]
+    fn parses_multiple_modules() {
+        let source = "module a(x,y); assign y = x; endmodule module b(x,y); assign y = !x; endmodule";
+        let modules = parse(source).unwrap();
+        assert_eq!(modules.len(), 2);
+    }
+}
@@ -1,13 +1,20 @@
 use egg::*;
 use serde::Deserialize;
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
 use std::path::Path;
 use std::str::FromStr;
 
+#[cfg(tree_sitter_grammar)]
+pub mod cst;
+mod diagnostics;
+mod frontend;
+mod repl;
+
+pub use diagnostics::{render_all, Diagnostic};
+pub use repl::Repl;
+
 // Represents a cell in a library.
 
 #[derive(Deserialize)]
@@ -20,20 +27,187 @@ struct Cell {
     applier: String,
 }
 
-// Load a library of cells from disk.
+// Load a library of cells from disk. Each cell's searcher/applier are
+// checked independently of the others, so one bad cell doesn't stop us from
+// reporting problems with the rest of the library in the same pass.
+
+fn load_library<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Cell>, Vec<Diagnostic>> {
+    let source = std::fs::read_to_string(&path).map_err(|e| {
+        vec![Diagnostic::new(
+            format!("failed to read library file: {}", e),
+            0..0,
+        )]
+    })?;
+    let cells: Vec<Cell> = serde_json::from_str(&source).map_err(|e| {
+        vec![Diagnostic::new(
+            format!("failed to parse library file as JSON: {}", e),
+            0..0,
+        )]
+    })?;
+
+    // `serde_json` does not retain spans, so scope each cell's diagnostics to
+    // its own `{...}` object by walking the raw text in lockstep with
+    // `cells`, rather than searching for the cell's name (which breaks on
+    // duplicate or overlapping names).
+    let spans = object_spans(&source);
+
+    let mut errors = vec![];
+    let mut library = HashMap::new();
+    for (cell, object_span) in cells.into_iter().zip(spans.into_iter().chain(std::iter::repeat(0..0))) {
+        let object_text = &source[object_span.start.min(source.len())..object_span.end.min(source.len())];
+
+        let searcher: Result<Pattern<BooleanLanguage>, _> = cell.searcher.parse();
+        if let Err(e) = &searcher {
+            let span = find_field_span(object_text, object_span.start, "searcher").unwrap_or(object_span.clone());
+            errors.push(Diagnostic::new(
+                format!("cell `{}` has an invalid searcher pattern: {}", cell.name, e),
+                span,
+            ));
+        }
+        let applier: Result<Pattern<BooleanLanguage>, _> = cell.applier.parse();
+        if let Err(e) = &applier {
+            let span = find_field_span(object_text, object_span.start, "applier").unwrap_or(object_span.clone());
+            errors.push(Diagnostic::new(
+                format!("cell `{}` has an invalid applier pattern: {}", cell.name, e),
+                span,
+            ));
+        }
 
-fn load_library<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Cell>, Box<dyn Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let cells: Vec<Cell> = serde_json::from_reader(reader)?;
+        // Cross-check that the searcher and applier agree on which pins they
+        // bind: an applier pin the searcher never bound is a dangling
+        // reference, and a searcher pin the applier never consumes is a
+        // dropped signal.
+        if let (Ok(searcher), Ok(applier)) = (&searcher, &applier) {
+            let searcher_vars = pattern_vars(searcher);
+            let applier_vars = pattern_vars(applier);
+            for var in applier_vars.difference(&searcher_vars) {
+                let span = find_field_span(object_text, object_span.start, "applier").unwrap_or(object_span.clone());
+                errors.push(Diagnostic::new(
+                    format!("cell `{}` applier references pin `{}` not declared in searcher", cell.name, var),
+                    span,
+                ));
+            }
+            for var in searcher_vars.difference(&applier_vars) {
+                let span = find_field_span(object_text, object_span.start, "searcher").unwrap_or(object_span.clone());
+                errors.push(Diagnostic::new(
+                    format!("cell `{}` searcher references pin `{}` not declared in applier", cell.name, var),
+                    span,
+                ));
+            }
+        }
 
-    let mut library = HashMap::new();
-    for cell in cells {
         library.insert(cell.name.clone(), cell);
     }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
     Ok(library)
 }
 
+// Collect every egg pattern variable (`?name`) bound or referenced by a
+// pattern.
+fn pattern_vars(pattern: &Pattern<BooleanLanguage>) -> HashSet<Var> {
+    pattern.vars().into_iter().collect()
+}
+
+// Locate the byte span of the string value of `field` within `object`, a
+// slice already scoped to a single cell's `{...}` object, and offset it back
+// into the enclosing source by `object_start`.
+fn find_field_span(object: &str, object_start: usize, field: &str) -> Option<std::ops::Range<usize>> {
+    let field_key_start = object.find(&format!("\"{}\"", field))?;
+    let colon = object[field_key_start..].find(':')? + field_key_start;
+    let value_start = object[colon..].find('"')? + colon + 1;
+    let value_end = object[value_start..].find('"')? + value_start;
+    Some(object_start + value_start..object_start + value_end)
+}
+
+// Compute the byte range of each top-level `{...}` object in a JSON array
+// literal, in source order, skipping braces that appear inside string
+// literals.
+fn object_spans(source: &str) -> Vec<std::ops::Range<usize>> {
+    let mut spans = vec![];
+    let mut depth = 0i32;
+    let mut start = None;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, ch) in source.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => {
+                if depth == 0 {
+                    start = Some(i);
+                }
+                depth += 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(s) = start.take() {
+                        spans.push(s..i + 1);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_spans_skips_braces_inside_strings() {
+        let source = r#"[{"name": "AND2", "pattern": "{not json}"}, {"name": "OR2"}]"#;
+        let spans = object_spans(source);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(&source[spans[0].clone()], r#"{"name": "AND2", "pattern": "{not json}"}"#);
+        assert_eq!(&source[spans[1].clone()], r#"{"name": "OR2"}"#);
+    }
+
+    #[test]
+    fn object_spans_skips_escaped_quotes() {
+        let source = r#"[{"name": "A\"B"}]"#;
+        let spans = object_spans(source);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(&source[spans[0].clone()], r#"{"name": "A\"B"}"#);
+    }
+
+    #[test]
+    fn find_field_span_locates_value_offset_into_source() {
+        let object = r#"{"name": "AND2", "searcher": "(& ?x ?y)"}"#;
+        let span = find_field_span(object, 100, "searcher").unwrap();
+        assert_eq!(&object[span.start - 100..span.end - 100], "(& ?x ?y)");
+    }
+
+    #[test]
+    fn find_field_span_missing_field_returns_none() {
+        let object = r#"{"name": "AND2"}"#;
+        assert_eq!(find_field_span(object, 0, "searcher"), None);
+    }
+
+    #[test]
+    fn pattern_vars_collects_every_egg_var() {
+        let pattern: Pattern<BooleanLanguage> = "(& ?x ?y)".parse().unwrap();
+        assert_eq!(pattern_vars(&pattern).len(), 2);
+    }
+}
+
 // A simple language for boolean logic and logic gates.
 
 define_language! {
@@ -57,6 +231,16 @@ pub struct BooleanExpression(pub RecExpr<BooleanLanguage>);
 pub struct BooleanEGraph(pub EGraph<BooleanLanguage, ()>);
 struct BooleanId(Id);
 
+impl BooleanExpression {
+    // Parse a structural netlist (e.g. `module top(a,b,y); assign y = a & b;
+    // endmodule`) directly into one `BooleanExpression` per module, without
+    // requiring callers to hand-assemble an s-expression.
+    pub fn parse_netlist(source: &str) -> Result<Vec<BooleanExpression>, frontend::ParseError> {
+        let modules = frontend::parse(source)?;
+        Ok(modules.into_iter().map(BooleanExpression).collect())
+    }
+}
+
 // A simpl cost function that prefers gates over boolean logic, and
 // literals or symbols the most. This is intended to push the search to optimize
 // the logic, then map to gates. Symbols are free to encourage reusing let
@@ -140,9 +324,10 @@ pub struct Synthesizer {
 }
 
 impl Synthesizer {
-    pub fn new(library_path: &str, metric_name: &str) -> Synthesizer {
-        let library = load_library(library_path).unwrap();
-        let metric = Metric::from_str(metric_name).unwrap();
+    pub fn new(library_path: &str, metric_name: &str) -> Result<Synthesizer, Vec<Diagnostic>> {
+        let library = load_library(library_path)?;
+        let metric = Metric::from_str(metric_name)
+            .map_err(|_| vec![Diagnostic::new(format!("unknown metric `{}`", metric_name), 0..0)])?;
 
         // Some axioms of Boolean logic. The goal is to allow exploration and
         // canonicalize towards right-associative DNF, which is how the logical
@@ -157,7 +342,9 @@ impl Synthesizer {
             multi_rewrite!("inline-let-not"; "?a = (let ?x ?y), ?b = (! ?x)" => "?b = (! ?y)"),
         ];
 
-        // Add rewrites from the library.
+        // Add rewrites from the library. `load_library` has already validated
+        // that every cell's searcher and applier parse, so these unwraps
+        // cannot fail here.
         for cell in library.values() {
             rules.push(rewrite!(cell.name; {
                 let searcher: Pattern<BooleanLanguage> = cell.searcher.parse().unwrap();
@@ -174,57 +361,88 @@ impl Synthesizer {
             library: library,
         };
 
-        Synthesizer {
+        Ok(Synthesizer {
             rules: rules,
             cost_function: cost_function,
-        }
+        })
     }
 
-    pub fn run(
+    // Switch the metric used for extraction. Lets a long-lived `Synthesizer`
+    // (as kept alive by the REPL) re-evaluate the same e-graph under a
+    // different metric without rebuilding the cell library.
+    pub fn set_metric(&mut self, metric_name: &str) -> Result<(), ()> {
+        self.cost_function.metric = Metric::from_str(metric_name)?;
+        Ok(())
+    }
+
+    // Run the rewrite rules over `egraph` until saturation or the runner's
+    // default iteration/node/time limits, seeded with
+    // `start_expr` as an additional root, and return the resulting `Runner`
+    // so callers can extract from or explain the e-graph it captured.
+    pub fn optimize(
         &self,
         mut egraph: BooleanEGraph,
-        start_expr: BooleanExpression,
-    ) -> BooleanExpression {
+        start_expr: &BooleanExpression,
+    ) -> Runner<BooleanLanguage, ()> {
         // Ensure the EGraph is ready after any mutations.
         egraph.0.rebuild();
 
-        // Run the optimizer with some debug info.
-        let mut runner = Runner::default()
+        Runner::default()
             .with_explanations_enabled()
             .with_egraph(egraph.0)
             .with_expr(&start_expr.0)
-            .run(&self.rules);
+            .run(&self.rules)
+    }
 
-        // Instantiate an extractor.
+    // Extract the best expression rooted at `root` from the e-graph captured
+    // by `runner`, using the synthesizer's current metric.
+    pub fn extract(&self, runner: &Runner<BooleanLanguage, ()>, root: Id) -> BooleanExpression {
         let mut extractor = LpExtractor::new(&runner.egraph, &self.cost_function);
+        BooleanExpression(extractor.solve(root))
+    }
 
-        // Extract the best expression.
-        let best_expr = extractor.solve(runner.roots[0]);
+    // Explain why `start_expr` and `best_expr` are equivalent, given the
+    // e-graph captured by `runner`.
+    pub fn explain(
+        &self,
+        runner: &mut Runner<BooleanLanguage, ()>,
+        start_expr: &BooleanExpression,
+        best_expr: &BooleanExpression,
+    ) -> String {
+        runner
+            .explain_equivalence(&start_expr.0, &best_expr.0)
+            .get_flat_string()
+    }
 
-        // Let explanations mutably borrow the runner.
-        drop(extractor);
+    // Render the e-graph captured by `runner` to an SVG file at `path`.
+    pub fn dot(&self, runner: &Runner<BooleanLanguage, ()>, path: &str) -> Result<(), Box<dyn Error>> {
+        runner
+            .egraph
+            .dot()
+            .with_config_line("ranksep=1")
+            .to_svg(path)?;
+        Ok(())
+    }
+
+    pub fn run(&self, egraph: BooleanEGraph, start_expr: BooleanExpression) -> BooleanExpression {
+        let mut runner = self.optimize(egraph, &start_expr);
+
+        let best_expr = self.extract(&runner, runner.roots[0]);
 
         // Provide some debug output.
         runner.print_report();
 
         println!(
             "Explanation\n===========\n{}",
-            runner
-                .explain_equivalence(&start_expr.0, &best_expr)
-                .get_flat_string()
+            self.explain(&mut runner, &start_expr, &best_expr)
         );
 
-        println!("\nResult\n======\n{}", best_expr);
+        println!("\nResult\n======\n{}", best_expr.0);
 
         // Produce a visualization of the EGraph.
-        runner
-            .egraph
-            .dot()
-            .with_config_line("ranksep=1")
-            .to_svg("egraph.svg")
-            .unwrap();
+        self.dot(&runner, "egraph.svg").unwrap();
 
-        BooleanExpression(best_expr)
+        best_expr
     }
 }
 
@@ -238,11 +456,16 @@ fn egraph_new() -> Box<BooleanEGraph> {
 
 // Synthesizer API.
 
-fn synthesizer_new(library_path: String, metric_name: String) -> Box<Synthesizer> {
-    Box::new(Synthesizer::new(
-        library_path.as_str(),
-        metric_name.as_str(),
-    ))
+fn synthesizer_new(library_path: String, metric_name: String) -> Result<Box<Synthesizer>, String> {
+    Synthesizer::new(library_path.as_str(), metric_name.as_str())
+        .map(Box::new)
+        .map_err(|diagnostics| {
+            diagnostics
+                .iter()
+                .map(Diagnostic::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
 }
 
 fn synthesizer_run(
@@ -467,7 +690,7 @@ mod ffi {
         fn egraph_new() -> Box<BooleanEGraph>;
 
         // Synthesizer API.
-        fn synthesizer_new(library_path: String, metric_name: String) -> Box<Synthesizer>;
+        fn synthesizer_new(library_path: String, metric_name: String) -> Result<Box<Synthesizer>>;
 
         fn synthesizer_run(
             egraph: Box<BooleanEGraph>,
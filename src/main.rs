@@ -1,17 +1,103 @@
 use egg::{EGraph, RecExpr};
-use egg_netlist_synthesizer::{BooleanEGraph, BooleanExpression, BooleanLanguage, Synthesizer};
+use egg_netlist_synthesizer::{render_all, BooleanEGraph, BooleanExpression, BooleanLanguage, Repl, Synthesizer};
 use std::env;
+use std::fs;
+use std::process;
+
+#[cfg(tree_sitter_grammar)]
+use egg_netlist_synthesizer::cst;
+
+// Validate a netlist file's syntax without running synthesis, using
+// tree-sitter's incremental parser.
+#[cfg(tree_sitter_grammar)]
+fn check(path: &str) {
+    let source =
+        fs::read_to_string(path).unwrap_or_else(|e| panic!("failed to read {}: {}", path, e));
+
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(cst::language())
+        .expect("failed to load the egg-netlist grammar");
+    let tree = parser.parse(&source, None).expect("tree-sitter failed to produce a tree");
+
+    match cst::cst_to_recexpr(&tree, &source) {
+        Ok(modules) => println!("ok ({} module{})", modules.len(), if modules.len() == 1 { "" } else { "s" }),
+        Err(diagnostics) => {
+            eprintln!("{}", render_all(&diagnostics, &source));
+            process::exit(1);
+        }
+    }
+}
+
+// `check` needs the tree-sitter grammar compiled in (see build.rs); without
+// it, say so instead of silently doing nothing.
+#[cfg(not(tree_sitter_grammar))]
+fn check(_path: &str) {
+    eprintln!(
+        "error: this binary was built without the tree-sitter grammar; \
+         see build.rs for how to enable the `check` subcommand"
+    );
+    process::exit(1);
+}
+
+fn build_synthesizer(library_path: &str, metric_name: &str) -> Synthesizer {
+    match Synthesizer::new(library_path, metric_name) {
+        Ok(synthesizer) => synthesizer,
+        Err(diagnostics) => {
+            let library_source = fs::read_to_string(library_path).unwrap_or_default();
+            eprintln!("{}", render_all(&diagnostics, &library_source));
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    let synthesizer = Synthesizer::new(&args[1], &args[2]);
+    if args.get(1).map(String::as_str) == Some("repl") {
+        let synthesizer = build_synthesizer(&args[2], &args[3]);
+        Repl::new(synthesizer).run();
+        return;
+    }
 
-    let expr: RecExpr<BooleanLanguage> = args[3].parse().unwrap();
+    if args.get(1).map(String::as_str) == Some("check") {
+        check(&args[2]);
+        return;
+    }
 
-    let egraph = EGraph::<BooleanLanguage, ()>::default().with_explanations_enabled();
+    let synthesizer = build_synthesizer(&args[1], &args[2]);
 
-    synthesizer.run(BooleanEGraph(egraph), BooleanExpression(expr));
+    let exprs = if args.get(3).map(String::as_str) == Some("--format") {
+        let format = args[4].as_str();
+        let path = &args[5];
+        match format {
+            "verilog" => {
+                let source = fs::read_to_string(path)
+                    .unwrap_or_else(|e| panic!("failed to read {}: {}", path, e));
+                match BooleanExpression::parse_netlist(&source) {
+                    Ok(exprs) => exprs,
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+            _ => panic!("unknown format `{}`", format),
+        }
+    } else {
+        match args[3].parse::<RecExpr<BooleanLanguage>>() {
+            Ok(parsed) => vec![BooleanExpression(parsed)],
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+    };
 
-    ()
+    // A source file can contain more than one module; synthesize each
+    // independently, starting from a fresh e-graph.
+    for expr in exprs {
+        let egraph = EGraph::<BooleanLanguage, ()>::default().with_explanations_enabled();
+        synthesizer.run(BooleanEGraph(egraph), expr);
+    }
 }
@@ -0,0 +1,178 @@
+// An interactive synthesis REPL. Keeps a single `Synthesizer` and the
+// e-graph built up across commands alive for the whole session, so `let`
+// bindings accumulate incrementally instead of being re-synthesized from
+// scratch on every input.
+//
+// Supports multiline entry: if parentheses are unbalanced (or the line ends
+// in `\`, which is dropped), the REPL keeps reading before attempting to
+// parse, so a `(module ...)` s-expression can be typed across several lines.
+// `:history` lists every command entered so far.
+
+use crate::{BooleanEGraph, BooleanExpression, BooleanLanguage, Synthesizer};
+use egg::{EGraph, RecExpr, Runner};
+use std::io::{self, BufRead, Write};
+
+pub struct Repl {
+    synthesizer: Synthesizer,
+    last_runner: Option<Runner<BooleanLanguage, ()>>,
+    last_expr: Option<RecExpr<BooleanLanguage>>,
+    history: Vec<String>,
+}
+
+impl Repl {
+    pub fn new(synthesizer: Synthesizer) -> Repl {
+        Repl {
+            synthesizer,
+            last_runner: None,
+            last_expr: None,
+            history: vec![],
+        }
+    }
+
+    // Read commands from stdin until EOF or `:quit`.
+    pub fn run(mut self) {
+        let stdin = io::stdin();
+        let mut buffer = String::new();
+
+        loop {
+            print_prompt(&buffer);
+
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                break;
+            }
+            buffer.push_str(line.trim_end_matches('\n'));
+
+            if needs_continuation(&buffer) {
+                if buffer.ends_with('\\') {
+                    buffer.pop();
+                }
+                buffer.push('\n');
+                continue;
+            }
+
+            let input = buffer.trim().to_string();
+            buffer.clear();
+
+            if input.is_empty() {
+                continue;
+            }
+            if input == ":quit" {
+                break;
+            }
+
+            self.history.push(input.clone());
+            self.handle(&input);
+        }
+    }
+
+    fn handle(&mut self, input: &str) {
+        if let Some(metric) = input.strip_prefix(":metric ") {
+            match self.synthesizer.set_metric(metric.trim()) {
+                Ok(()) => println!("metric set to {}", metric.trim()),
+                Err(()) => eprintln!("error: unknown metric `{}`", metric.trim()),
+            }
+        } else if input == ":extract" {
+            self.extract();
+        } else if input == ":explain" {
+            self.explain();
+        } else if let Some(path) = input.strip_prefix(":dot ") {
+            self.dot(path.trim());
+        } else if input == ":history" {
+            self.history();
+        } else {
+            self.add(input);
+        }
+    }
+
+    // Parse `source` as an expression, run the rewrite rules over it, and
+    // fold the result into the persistent e-graph.
+    fn add(&mut self, source: &str) {
+        let expr = match source.parse::<RecExpr<BooleanLanguage>>() {
+            Ok(expr) => expr,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return;
+            }
+        };
+
+        let egraph = match self.last_runner.take() {
+            Some(runner) => BooleanEGraph(runner.egraph),
+            None => BooleanEGraph(EGraph::default().with_explanations_enabled()),
+        };
+
+        let runner = self
+            .synthesizer
+            .optimize(egraph, &BooleanExpression(expr.clone()));
+        self.last_expr = Some(expr);
+        self.last_runner = Some(runner);
+        println!("ok");
+    }
+
+    fn extract(&self) {
+        match &self.last_runner {
+            Some(runner) => {
+                let best = self.synthesizer.extract(runner, runner.roots[0]);
+                println!("{}", best.0);
+            }
+            None => eprintln!("error: nothing to extract yet; add a module first"),
+        }
+    }
+
+    fn explain(&mut self) {
+        let start_expr = match &self.last_expr {
+            Some(expr) => BooleanExpression(expr.clone()),
+            None => {
+                eprintln!("error: nothing to explain yet; add a module first");
+                return;
+            }
+        };
+
+        match &mut self.last_runner {
+            Some(runner) => {
+                let best_expr = self.synthesizer.extract(runner, runner.roots[0]);
+                let explanation = self.synthesizer.explain(runner, &start_expr, &best_expr);
+                println!("{}", explanation);
+            }
+            None => eprintln!("error: nothing to explain yet; add a module first"),
+        }
+    }
+
+    fn dot(&self, path: &str) {
+        match &self.last_runner {
+            Some(runner) => match self.synthesizer.dot(runner, path) {
+                Ok(()) => println!("wrote {}", path),
+                Err(e) => eprintln!("error: {}", e),
+            },
+            None => eprintln!("error: nothing to render yet; add a module first"),
+        }
+    }
+
+    // List every command entered so far this session, most recent last.
+    fn history(&self) {
+        for (i, entry) in self.history.iter().enumerate() {
+            println!("{:>4}  {}", i + 1, entry);
+        }
+    }
+}
+
+fn needs_continuation(buffer: &str) -> bool {
+    if buffer.ends_with('\\') {
+        return true;
+    }
+
+    let mut depth = 0i32;
+    for c in buffer.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    depth > 0
+}
+
+fn print_prompt(buffer: &str) {
+    print!("{}", if buffer.is_empty() { "egg> " } else { "...> " });
+    io::stdout().flush().ok();
+}